@@ -5,6 +5,7 @@ use penumbra_chain::{quarantined::Scheduled, CompactBlock, NoteSource};
 use penumbra_component::shielded_pool::Delible;
 use penumbra_crypto::Nullifier;
 use penumbra_proto::Protobuf;
+use penumbra_storage2::{Snapshot, Storage};
 use penumbra_tct::Commitment;
 
 #[derive(Debug, clap::Subcommand)]
@@ -40,12 +41,25 @@ pub enum ShieldedPool {
         /// The nullifier to query.
         #[clap(parse(try_from_str = Nullifier::parse_hex))]
         nullifier: Nullifier,
+        /// If set, query as of this past block height instead of the chain tip.
+        ///
+        /// Unlike the anchor/compact-block queries above, a nullifier's spent
+        /// status is stored at a single key that's overwritten as state changes,
+        /// so answering "was this nullifier spent as of height N" requires
+        /// resolving `at_height` to a JMT version and reading through a
+        /// `Storage::snapshot_at` pinned there, rather than a plain latest-state
+        /// lookup.
+        #[clap(long)]
+        at_height: Option<u64>,
     },
     /// Queries the note source of a given quarantined nullifier.
     QuarantinedNullifier {
         /// The nullifier to query.
         #[clap(parse(try_from_str = Nullifier::parse_hex))]
         nullifier: Nullifier,
+        /// If set, query as of this past block height instead of the chain tip.
+        #[clap(long)]
+        at_height: Option<u64>,
     },
     /// Queries the compact block at a given height.
     CompactBlock { height: u64 },
@@ -61,13 +75,44 @@ impl ShieldedPool {
             ShieldedPool::CompactBlock { height } => state_key::compact_block(*height),
             ShieldedPool::Scheduled { epoch } => state_key::scheduled_to_apply(*epoch),
             ShieldedPool::Commitment { commitment } => state_key::note_source(*commitment),
-            ShieldedPool::Nullifier { nullifier } => state_key::spent_nullifier_lookup(*nullifier),
-            ShieldedPool::QuarantinedNullifier { nullifier } => {
+            ShieldedPool::Nullifier { nullifier, .. } => {
+                state_key::spent_nullifier_lookup(*nullifier)
+            }
+            ShieldedPool::QuarantinedNullifier { nullifier, .. } => {
                 state_key::quarantined_spent_nullifier_lookup(*nullifier)
             }
         }
     }
 
+    /// The past block height this query should be answered as of, if any.
+    ///
+    /// A caller with a `Storage` handle resolves this to a JMT version via
+    /// `Storage::version_for_height` and reads through `Storage::snapshot_at`
+    /// rather than `Storage::latest_snapshot`. See `resolve_snapshot`, which
+    /// does exactly that.
+    pub fn at_height(&self) -> Option<u64> {
+        match self {
+            ShieldedPool::Nullifier { at_height, .. } => *at_height,
+            ShieldedPool::QuarantinedNullifier { at_height, .. } => *at_height,
+            _ => None,
+        }
+    }
+
+    /// Resolves the `Snapshot` this query should be answered against: the
+    /// latest one, or, if `at_height` was given, the historical one pinned to
+    /// that height's JMT version.
+    ///
+    /// Every query dispatch path should call this instead of
+    /// `storage.latest_snapshot()` directly; `key_hash` does not depend on
+    /// `at_height`. On the returned snapshot, only `get_raw_with_proof` reads at
+    /// the pinned version; see `Storage::snapshot_at`.
+    pub fn resolve_snapshot(&self, storage: &Storage) -> Result<Snapshot> {
+        match self.at_height() {
+            Some(height) => storage.snapshot_at(storage.version_for_height(height)),
+            None => storage.latest_snapshot(),
+        }
+    }
+
     pub fn display_value(&self, bytes: &[u8]) -> Result<()> {
         let json = match self {
             ShieldedPool::Anchor { .. } => {
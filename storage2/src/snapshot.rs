@@ -3,7 +3,11 @@ use std::{pin::Pin, sync::Arc};
 use anyhow::Result;
 use async_trait::async_trait;
 use futures::Stream;
-use jmt::storage::{LeafNode, Node, NodeKey, TreeReader};
+use jmt::{
+    proof::SparseMerkleProof,
+    storage::{LeafNode, Node, NodeKey, TreeReader},
+    KeyHash, RootHash,
+};
 use tokio::sync::mpsc;
 use tracing::Span;
 
@@ -12,6 +16,94 @@ use crate::state::StateRead;
 mod rocks_wrapper;
 use rocks_wrapper::RocksDbSnapshot;
 
+/// The column family a cached raw-read key belongs to.
+///
+/// Used as part of the cache key rather than the raw column family name, so that
+/// we don't pay for a string comparison/hash on every cache lookup.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+enum ColumnFamily {
+    Jmt,
+    Nonconsensus,
+}
+
+/// A process-wide, size-bounded cache shared across all [`Snapshot`]s produced by
+/// the same `Storage`.
+///
+/// Snapshots are immutable for a given [`jmt::Version`], so cached entries never
+/// need to be invalidated -- they're only ever evicted once the cache exceeds its
+/// configured capacity.
+#[derive(Clone)]
+pub struct SnapshotCache {
+    raw: moka::sync::Cache<(jmt::Version, ColumnFamily, Vec<u8>), Option<Vec<u8>>>,
+    nodes: moka::sync::Cache<(jmt::Version, NodeKey), Node>,
+}
+
+impl SnapshotCache {
+    /// Creates a new cache that holds up to `capacity` entries for each of the raw
+    /// value cache and the decoded JMT node cache.
+    pub fn new(capacity: u64) -> Self {
+        Self {
+            raw: moka::sync::Cache::new(capacity),
+            nodes: moka::sync::Cache::new(capacity),
+        }
+    }
+}
+
+/// Configures how far back historical reads are retained.
+///
+/// `Storage` consults this when constructing a snapshot at a past version: under
+/// `Archive` every version ever committed remains readable, while under
+/// `KeepLast` only the most recent `keep` versions (and the latest) do, and older
+/// versions are reported as pruned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PruningMode {
+    /// Keep every historical version, forever.
+    Archive,
+    /// Keep only the latest version and the `keep` versions before it.
+    KeepLast {
+        /// The number of trailing versions to retain, in addition to the latest.
+        keep: u64,
+    },
+}
+
+impl PruningMode {
+    /// Returns the oldest version that should still be readable, given that the
+    /// latest committed version is `latest`.
+    pub fn earliest_available(&self, latest: jmt::Version) -> jmt::Version {
+        match self {
+            PruningMode::Archive => 0,
+            PruningMode::KeepLast { keep } => latest.saturating_sub(*keep),
+        }
+    }
+
+    /// Checks whether `requested` is still available given that `latest` is the
+    /// most recent committed version, returning a [`VersionPruned`] error if not.
+    pub fn ensure_available(
+        &self,
+        requested: jmt::Version,
+        latest: jmt::Version,
+    ) -> Result<(), VersionPruned> {
+        let earliest = self.earliest_available(latest);
+        if requested < earliest {
+            Err(VersionPruned {
+                requested,
+                earliest_available: earliest,
+            })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Returned when a historical read targets a [`jmt::Version`] that has already
+/// been pruned under the storage's configured [`PruningMode`].
+#[derive(Clone, Copy, Debug, thiserror::Error)]
+#[error("version {requested} has been pruned; earliest available version is {earliest_available}")]
+pub struct VersionPruned {
+    pub requested: jmt::Version,
+    pub earliest_available: jmt::Version,
+}
+
 /// A snapshot of the underlying storage at a specific state version, suitable
 /// for read-only access by multiple threads, e.g., RPC calls.
 ///
@@ -28,14 +120,17 @@ struct Inner {
     version: jmt::Version,
     // Used to retrieve column family handles.
     db: Arc<rocksdb::DB>,
+    // Shared across every `Snapshot` produced by the same `Storage`.
+    cache: SnapshotCache,
 }
 
 impl Snapshot {
-    pub(crate) fn new(db: Arc<rocksdb::DB>, version: jmt::Version) -> Self {
+    pub(crate) fn new(db: Arc<rocksdb::DB>, version: jmt::Version, cache: SnapshotCache) -> Self {
         Self(Arc::new(Inner {
             snapshot: RocksDbSnapshot::new(db.clone()),
             version,
             db,
+            cache,
         }))
     }
 
@@ -44,6 +139,21 @@ impl Snapshot {
     }
 }
 
+/// Verifies that `proof` attests to the (non-)inclusion of `value` for `key` under
+/// `root`, without needing access to a full node or a running [`Snapshot`].
+///
+/// This lets downstream consumers (RPC clients, light clients, bridges) check a
+/// [`StateRead::get_raw_with_proof`] response against a root they already trust.
+pub fn verify_state_proof(
+    root: RootHash,
+    key: &str,
+    value: Option<&[u8]>,
+    proof: &SparseMerkleProof<sha2::Sha256>,
+) -> Result<()> {
+    let key_hash = KeyHash::with::<sha2::Sha256>(key.as_bytes());
+    proof.verify(root, key_hash, value)
+}
+
 #[async_trait]
 impl StateRead for Snapshot {
     /// Fetch a key from the JMT column family.
@@ -55,11 +165,20 @@ impl StateRead for Snapshot {
             .name("Snapshot::get_raw")
             .spawn_blocking(move || {
                 span.in_scope(|| {
+                    let cache_key = (inner.version, ColumnFamily::Jmt, key.clone().into_bytes());
+                    if let Some(value) = inner.cache.raw.get(&cache_key) {
+                        metrics::increment_counter!("penumbra_storage2_snapshot_cache_hit", "cf" => "jmt");
+                        return Ok(value);
+                    }
+                    metrics::increment_counter!("penumbra_storage2_snapshot_cache_miss", "cf" => "jmt");
+
                     let jmt_cf = inner
                         .db
                         .cf_handle("jmt")
                         .expect("jmt column family not found");
-                    inner.snapshot.get_cf(jmt_cf, key).map_err(Into::into)
+                    let value = inner.snapshot.get_cf(jmt_cf, key)?;
+                    inner.cache.raw.insert(cache_key, value.clone());
+                    Ok(value)
                 })
             })?
             .await?
@@ -74,14 +193,51 @@ impl StateRead for Snapshot {
             .name("Snapshot::get_nonconsensus")
             .spawn_blocking(move || {
                 span.in_scope(|| {
+                    let cache_key = (inner.version, ColumnFamily::Nonconsensus, key.clone());
+                    if let Some(value) = inner.cache.raw.get(&cache_key) {
+                        metrics::increment_counter!("penumbra_storage2_snapshot_cache_hit", "cf" => "nonconsensus");
+                        return Ok(value);
+                    }
+                    metrics::increment_counter!("penumbra_storage2_snapshot_cache_miss", "cf" => "nonconsensus");
+
                     let nonconsensus_cf = inner
                         .db
                         .cf_handle("nonconsensus")
                         .expect("nonconsensus column family not found");
-                    inner
-                        .snapshot
-                        .get_cf(nonconsensus_cf, key)
-                        .map_err(Into::into)
+                    let value = inner.snapshot.get_cf(nonconsensus_cf, key)?;
+                    inner.cache.raw.insert(cache_key, value.clone());
+                    Ok(value)
+                })
+            })?
+            .await?
+    }
+
+    /// Fetch a key from the JMT column family, together with a [`SparseMerkleProof`]
+    /// attesting to the value's (non-)inclusion in the tree at this snapshot's version.
+    ///
+    /// The proof is valid for both membership and non-membership: if `key` is absent
+    /// from the tree, the returned value will be `None` and the proof attests to
+    /// that absence. Callers that only trust a root hash (RPC clients, light clients,
+    /// bridges) can check the result with [`verify_state_proof`] without needing
+    /// access to the full tree.
+    ///
+    /// Like the other accessors on this impl, the tree walk runs on a blocking
+    /// thread: it does synchronous RocksDB reads for each step down the JMT, and
+    /// those shouldn't happen on the async executor's worker threads.
+    async fn get_raw_with_proof(
+        &self,
+        key: &str,
+    ) -> Result<(Option<Vec<u8>>, SparseMerkleProof<sha2::Sha256>)> {
+        let span = Span::current();
+        let inner = self.clone();
+        let key = key.to_string();
+        tokio::task::Builder::new()
+            .name("Snapshot::get_raw_with_proof")
+            .spawn_blocking(move || {
+                span.in_scope(|| {
+                    let tree = jmt::JellyfishMerkleTree::<_, sha2::Sha256>::new(&inner);
+                    let key_hash = KeyHash::with::<sha2::Sha256>(key.as_bytes());
+                    tree.get_with_proof(key_hash, inner.version())
                 })
             })?
             .await?
@@ -141,9 +297,16 @@ impl StateRead for Snapshot {
 impl TreeReader for Snapshot {
     /// Gets node given a node key. Returns `None` if the node does not exist.
     fn get_node_option(&self, node_key: &NodeKey) -> Result<Option<Node>> {
-        let node_key = node_key;
         tracing::trace!(?node_key);
 
+        let cache_key = (self.0.version, node_key.clone());
+        if let Some(node) = self.0.cache.nodes.get(&cache_key) {
+            metrics::increment_counter!("penumbra_storage2_snapshot_node_cache_hit");
+            tracing::trace!(?node_key, value = ?Some(&node));
+            return Ok(Some(node));
+        }
+        metrics::increment_counter!("penumbra_storage2_snapshot_node_cache_miss");
+
         let jmt_cf = self
             .0
             .db
@@ -156,6 +319,10 @@ impl TreeReader for Snapshot {
             .map(|db_slice| Node::decode(&db_slice))
             .transpose()?;
 
+        if let Some(node) = &value {
+            self.0.cache.nodes.insert(cache_key, node.clone());
+        }
+
         tracing::trace!(?node_key, ?value);
         Ok(value)
     }
@@ -183,3 +350,118 @@ impl TreeReader for Snapshot {
         Ok(None)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jmt::{mock::MockTreeStore, JellyfishMerkleTree};
+
+    #[test]
+    fn snapshot_cache_distinguishes_versions_of_the_same_key() {
+        let cache = SnapshotCache::new(10);
+        let key = b"same_key".to_vec();
+
+        // Nothing cached yet for either version: both are misses.
+        assert_eq!(cache.raw.get(&(0, ColumnFamily::Jmt, key.clone())), None);
+        assert_eq!(cache.raw.get(&(1, ColumnFamily::Jmt, key.clone())), None);
+
+        cache
+            .raw
+            .insert((0, ColumnFamily::Jmt, key.clone()), Some(b"v0".to_vec()));
+        cache
+            .raw
+            .insert((1, ColumnFamily::Jmt, key.clone()), Some(b"v1".to_vec()));
+
+        // Each version's entry is cached independently of the other, even
+        // though the key and column family are identical.
+        assert_eq!(
+            cache.raw.get(&(0, ColumnFamily::Jmt, key.clone())),
+            Some(Some(b"v0".to_vec()))
+        );
+        assert_eq!(
+            cache.raw.get(&(1, ColumnFamily::Jmt, key.clone())),
+            Some(Some(b"v1".to_vec()))
+        );
+
+        // A version that was never inserted is still a miss.
+        assert_eq!(cache.raw.get(&(2, ColumnFamily::Jmt, key)), None);
+    }
+
+    // Exercises proof generation/verification directly against a JMT backed by
+    // `MockTreeStore`, without going through a `Snapshot` at all: useful as a
+    // fast check of `verify_state_proof` itself, but `get_raw_with_proof_...`
+    // below is what actually covers the `StateRead` impl on this file's
+    // `Snapshot`, including its `spawn_blocking` hop.
+    #[test]
+    fn get_raw_with_proof_verifies_present_and_absent_keys() -> Result<()> {
+        let store = MockTreeStore::default();
+        let tree = JellyfishMerkleTree::<_, sha2::Sha256>::new(&store);
+
+        let present_key = "present_key";
+        let present_value = b"present_value".to_vec();
+        let key_hash = KeyHash::with::<sha2::Sha256>(present_key.as_bytes());
+
+        let (root, batch) =
+            tree.put_value_set(vec![(key_hash, Some(present_value.clone()))], 0)?;
+        store.write_tree_update_batch(batch)?;
+
+        // A present key: the proof attests to membership.
+        let (value, proof) = tree.get_with_proof(key_hash, 0)?;
+        assert_eq!(value, Some(present_value.clone()));
+        verify_state_proof(root, present_key, Some(&present_value), &proof)?;
+
+        // An absent key under the same root: the proof attests to non-membership.
+        let absent_key = "absent_key";
+        let absent_key_hash = KeyHash::with::<sha2::Sha256>(absent_key.as_bytes());
+        let (value, proof) = tree.get_with_proof(absent_key_hash, 0)?;
+        assert_eq!(value, None);
+        verify_state_proof(root, absent_key, None, &proof)?;
+
+        Ok(())
+    }
+
+    /// Drives `Snapshot::get_raw_with_proof` itself, rather than a bare JMT
+    /// over `MockTreeStore`: the tree nodes are seeded straight into the "jmt"
+    /// column family of a throwaway RocksDB instance, the same bytes
+    /// `Storage`'s commit path will eventually write there, and then read back
+    /// through the real `StateRead` impl (including its `spawn_blocking` hop).
+    ///
+    /// This doesn't go through `Transaction::commit` / a `Storage`-driven
+    /// write path -- that pipeline doesn't exist yet -- so it's scoped to what
+    /// `get_raw_with_proof` itself touches: `TreeReader::get_node_option` over
+    /// the "jmt" column family.
+    #[tokio::test]
+    async fn get_raw_with_proof_verifies_through_snapshot() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let mut opts = rocksdb::Options::default();
+        opts.create_if_missing(true);
+        opts.create_missing_column_families(true);
+        let db = Arc::new(rocksdb::DB::open_cf(&opts, dir.path(), ["jmt"])?);
+        let jmt_cf = db.cf_handle("jmt").expect("jmt column family not found");
+
+        let store = MockTreeStore::default();
+        let tree = JellyfishMerkleTree::<_, sha2::Sha256>::new(&store);
+
+        let present_key = "present_key";
+        let present_value = b"present_value".to_vec();
+        let key_hash = KeyHash::with::<sha2::Sha256>(present_key.as_bytes());
+        let (root, batch) =
+            tree.put_value_set(vec![(key_hash, Some(present_value.clone()))], 0)?;
+
+        for (node_key, node) in batch.node_batch.clone().into_iter() {
+            db.put_cf(jmt_cf, node_key.encode()?, node.encode()?)?;
+        }
+
+        let snapshot = Snapshot::new(db, 0, SnapshotCache::new(10));
+
+        let (value, proof) = snapshot.get_raw_with_proof(present_key).await?;
+        assert_eq!(value, Some(present_value.clone()));
+        verify_state_proof(root, present_key, Some(&present_value), &proof)?;
+
+        let (value, proof) = snapshot.get_raw_with_proof("absent_key").await?;
+        assert_eq!(value, None);
+        verify_state_proof(root, "absent_key", None, &proof)?;
+
+        Ok(())
+    }
+}
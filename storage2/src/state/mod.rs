@@ -0,0 +1,140 @@
+use std::{collections::BTreeMap, pin::Pin};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::Stream;
+
+use crate::snapshot::Snapshot;
+
+pub(crate) mod read;
+mod transaction;
+
+pub use transaction::{ChangeEvent, ChangeSubscriptions, NonconsensusChangeEvent, Transaction};
+
+/// Read access to chain state.
+#[async_trait]
+pub trait StateRead: Send + Sync {
+    /// Fetch a key from consensus-critical state (stored in the JMT).
+    async fn get_raw(&self, key: &str) -> Result<Option<Vec<u8>>>;
+
+    /// Fetch a key from non-consensus-critical state.
+    async fn get_nonconsensus(&self, key: &[u8]) -> Result<Option<Vec<u8>>>;
+
+    /// Fetch all keys matching a prefix from consensus-critical state.
+    async fn prefix_raw<'a>(
+        &'a self,
+        prefix: &'a str,
+    ) -> Pin<Box<dyn Stream<Item = Result<(String, Box<[u8]>)>> + Sync + Send + 'a>>;
+
+    /// Fetch a key from consensus-critical state, together with a cryptographic
+    /// proof of its (non-)inclusion in the JMT.
+    ///
+    /// Only a [`crate::Snapshot`], which is backed by an actual JMT, can produce a
+    /// proof; [`State`] delegates to its underlying `Snapshot`. Other implementors
+    /// (e.g. [`Transaction`]) are in-memory RYW overlays with nothing to prove
+    /// against, so the default implementation reports the capability as
+    /// unsupported rather than silently omitting the proof.
+    async fn get_raw_with_proof(
+        &self,
+        _key: &str,
+    ) -> Result<(Option<Vec<u8>>, jmt::proof::SparseMerkleProof<sha2::Sha256>)> {
+        Err(anyhow::anyhow!(
+            "this StateRead implementation does not support inclusion proofs"
+        ))
+    }
+
+    /// Subscribes to committed changes to any key starting with `prefix`.
+    ///
+    /// The default implementation returns an empty stream, which is the correct
+    /// behavior for a read-only, already-frozen view like [`crate::Snapshot`]: by
+    /// definition nothing will ever change under a pinned version. Implementors
+    /// backed by a live [`ChangeSubscriptions`] (e.g. [`Transaction`]) override
+    /// this to actually fan out commits.
+    fn subscribe_prefix<'a>(
+        &'a self,
+        _prefix: &'a str,
+    ) -> Pin<Box<dyn Stream<Item = Result<(String, Option<Vec<u8>>)>> + Sync + Send + 'a>> {
+        Box::pin(futures::stream::empty())
+    }
+}
+
+/// Write access to chain state.
+pub trait StateWrite {
+    fn put_raw(&mut self, key: String, value: jmt::OwnedValue);
+    fn delete(&mut self, key: String);
+    fn put_nonconsensus(&mut self, key: Vec<u8>, value: Vec<u8>);
+    fn delete_nonconsensus(&mut self, key: Vec<u8>);
+}
+
+/// The authoritative view of chain state: a [`Snapshot`] of everything already
+/// committed to RocksDB, plus whatever [`Transaction`]s built on top of it have
+/// folded in via `Transaction::commit` but that `Storage` hasn't yet flushed to
+/// a new JMT version.
+///
+/// Every `Transaction` forked from a `State` shares its [`ChangeSubscriptions`]
+/// bus, so that a subscriber only ever has to register interest once, against
+/// the `State`, rather than against each `Transaction` built on top of it.
+pub struct State {
+    pub(crate) unwritten_changes: BTreeMap<String, Option<Vec<u8>>>,
+    pub(crate) nonconsensus_changes: BTreeMap<Vec<u8>, Option<Vec<u8>>>,
+    snapshot: Snapshot,
+    subscriptions: ChangeSubscriptions,
+}
+
+impl State {
+    pub(crate) fn new(snapshot: Snapshot, subscriptions: ChangeSubscriptions) -> Self {
+        Self {
+            unwritten_changes: BTreeMap::new(),
+            nonconsensus_changes: BTreeMap::new(),
+            snapshot,
+            subscriptions,
+        }
+    }
+
+    /// Forks a new [`Transaction`] over this `State`, wired to the same
+    /// [`ChangeSubscriptions`] bus so that `Transaction::commit` can publish to it.
+    pub fn begin_transaction(&mut self) -> Transaction<'_> {
+        Transaction::new(self, self.subscriptions.clone())
+    }
+}
+
+#[async_trait]
+impl StateRead for State {
+    async fn get_raw(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        if let Some(v) = self.unwritten_changes.get(key) {
+            return Ok(v.clone());
+        }
+        self.snapshot.get_raw(key).await
+    }
+
+    async fn get_nonconsensus(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        if let Some(v) = self.nonconsensus_changes.get(key) {
+            return Ok(v.clone());
+        }
+        self.snapshot.get_nonconsensus(key).await
+    }
+
+    async fn prefix_raw<'a>(
+        &'a self,
+        prefix: &'a str,
+    ) -> Pin<Box<dyn Stream<Item = Result<(String, Box<[u8]>)>> + Sync + Send + 'a>> {
+        read::prefix_raw_with_cache(&self.snapshot, &self.unwritten_changes, prefix).await
+    }
+
+    // Proofs are only ever asked of committed data, so this delegates straight to
+    // the underlying `Snapshot` rather than trying to prove anything about
+    // `unwritten_changes`, which by definition aren't in the JMT yet.
+    async fn get_raw_with_proof(
+        &self,
+        key: &str,
+    ) -> Result<(Option<Vec<u8>>, jmt::proof::SparseMerkleProof<sha2::Sha256>)> {
+        self.snapshot.get_raw_with_proof(key).await
+    }
+
+    fn subscribe_prefix<'a>(
+        &'a self,
+        prefix: &'a str,
+    ) -> Pin<Box<dyn Stream<Item = Result<(String, Option<Vec<u8>>)>> + Sync + Send + 'a>> {
+        self.subscriptions.subscribe_prefix(prefix)
+    }
+}
@@ -0,0 +1,50 @@
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use futures::StreamExt;
+
+use super::StateRead;
+
+/// Combines a prefix scan of `underlying` with a pending RYW `overlay`, so that
+/// unwritten writes are visible to a prefix query without having to flush them
+/// first.
+///
+/// Entries in `overlay` take priority over whatever `underlying` returns for the
+/// same key, since they're more recent; a pending delete (`None` in the overlay)
+/// suppresses the underlying value entirely. Shared by both [`super::Transaction`]
+/// (overlaying its `unwritten_changes` on its parent `State`) and [`super::State`]
+/// (overlaying its own `unwritten_changes` on its `Snapshot`).
+pub(crate) async fn prefix_raw_with_cache<'a, T: StateRead>(
+    underlying: &'a T,
+    overlay: &'a BTreeMap<String, Option<Vec<u8>>>,
+    prefix: &'a str,
+) -> std::pin::Pin<
+    Box<dyn futures::Stream<Item = Result<(String, Box<[u8]>)>> + Sync + Send + 'a>,
+> {
+    let mut results = Vec::new();
+
+    for (key, value) in overlay.range(prefix.to_string()..) {
+        if !key.starts_with(prefix) {
+            break;
+        }
+        if let Some(value) = value {
+            results.push(Ok((key.clone(), Box::from(value.as_slice()))));
+        }
+    }
+
+    let overridden: std::collections::BTreeSet<&String> = overlay
+        .range(prefix.to_string()..)
+        .take_while(|(key, _)| key.starts_with(prefix))
+        .map(|(key, _)| key)
+        .collect();
+
+    let mut underlying = underlying.prefix_raw(prefix).await;
+    while let Some(item) = underlying.next().await {
+        match &item {
+            Ok((key, _)) if overridden.contains(key) => continue,
+            _ => results.push(item),
+        }
+    }
+
+    Box::pin(tokio_stream::iter(results))
+}
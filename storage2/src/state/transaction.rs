@@ -1,12 +1,148 @@
 use anyhow::Result;
 use async_trait::async_trait;
-use futures::Stream;
-use std::{collections::BTreeMap, pin::Pin};
+use futures::{Stream, StreamExt};
+use std::{
+    collections::BTreeMap,
+    pin::Pin,
+    sync::{Arc, Mutex},
+};
+use tokio::sync::broadcast;
+use tokio_stream::wrappers::BroadcastStream;
 
 use crate::State;
 
 use super::{read::prefix_raw_with_cache, StateRead, StateWrite};
 
+/// A single change to a consensus-critical key, published when the transaction
+/// that made it commits.
+#[derive(Clone, Debug)]
+pub struct ChangeEvent {
+    pub key: String,
+    pub value: Option<Vec<u8>>,
+}
+
+/// A single change to a nonconsensus key, published when the transaction that
+/// made it commits.
+///
+/// Kept separate from [`ChangeEvent`], and its keys matched as raw bytes rather
+/// than funneled through the consensus string-keyed bus: nonconsensus keys are
+/// arbitrary bytes (mirroring `StateRead::get_nonconsensus`'s `&[u8]`), and
+/// lossily decoding them to UTF-8 for the sake of matching a `String` prefix
+/// both risks collisions between distinct binary keys and leaves subscribers
+/// unable to express the prefix they actually want to match.
+#[derive(Clone, Debug)]
+pub struct NonconsensusChangeEvent {
+    pub key: Vec<u8>,
+    pub value: Option<Vec<u8>>,
+}
+
+/// A fan-out bus of committed state changes, keyed by prefix.
+///
+/// Callers register interest in a key prefix with `subscribe_prefix` (consensus
+/// keys) or `subscribe_nonconsensus_prefix` (nonconsensus keys), then receive
+/// every future change to a key starting with that prefix. Because subscriptions
+/// are fed directly from `Transaction::commit`, consumers (reactive indexers,
+/// block explorers) can follow state mutations -- new `note_source`s, spent
+/// nullifiers, anchors -- without repeatedly re-scanning RocksDB via `prefix_raw`.
+///
+/// A `ChangeSubscriptions` is meant to be created once per `State` and shared
+/// (via `clone`, which is cheap) across every `Transaction` built on top of it.
+#[derive(Clone, Default)]
+pub struct ChangeSubscriptions {
+    subscribers: Arc<Mutex<Vec<(String, broadcast::Sender<ChangeEvent>)>>>,
+    nonconsensus_subscribers: Arc<Mutex<Vec<(Vec<u8>, broadcast::Sender<NonconsensusChangeEvent>)>>>,
+}
+
+impl ChangeSubscriptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers interest in changes to any key starting with `prefix`, returning
+    /// a stream of the matching changes committed from this point on.
+    pub fn subscribe_prefix<'a>(
+        &'a self,
+        prefix: &'a str,
+    ) -> Pin<Box<dyn Stream<Item = Result<(String, Option<Vec<u8>>)>> + Sync + Send + 'a>> {
+        let (tx, rx) = broadcast::channel(128);
+        self.subscribers
+            .lock()
+            .expect("subscriptions mutex is not poisoned")
+            .push((prefix.to_string(), tx));
+
+        Box::pin(BroadcastStream::new(rx).map(|event| match event {
+            Ok(ChangeEvent { key, value }) => Ok((key, value)),
+            Err(e) => Err(anyhow::Error::from(e)),
+        }))
+    }
+
+    /// As `subscribe_prefix`, but for nonconsensus keys, matched as raw bytes.
+    pub fn subscribe_nonconsensus_prefix<'a>(
+        &'a self,
+        prefix: &'a [u8],
+    ) -> Pin<Box<dyn Stream<Item = Result<(Vec<u8>, Option<Vec<u8>>)>> + Sync + Send + 'a>> {
+        let (tx, rx) = broadcast::channel(128);
+        self.nonconsensus_subscribers
+            .lock()
+            .expect("subscriptions mutex is not poisoned")
+            .push((prefix.to_vec(), tx));
+
+        Box::pin(BroadcastStream::new(rx).map(|event| match event {
+            Ok(NonconsensusChangeEvent { key, value }) => Ok((key, value)),
+            Err(e) => Err(anyhow::Error::from(e)),
+        }))
+    }
+
+    /// Publishes a single consensus key change to every subscriber whose prefix
+    /// matches, using the same prefix-matching rule as `StateRead::prefix_raw`.
+    ///
+    /// Subscribers whose stream has been dropped (no receivers left) are pruned
+    /// from the list here rather than lingering forever: indexers and RPC streams
+    /// subscribe and disconnect repeatedly over a node's lifetime, and without
+    /// this every commit would keep scanning an ever-growing list of dead senders.
+    fn publish(&self, key: &str, value: &Option<Vec<u8>>) {
+        let mut subscribers = self
+            .subscribers
+            .lock()
+            .expect("subscriptions mutex is not poisoned");
+        subscribers.retain(|(prefix, tx)| {
+            if tx.receiver_count() == 0 {
+                return false;
+            }
+            if key.starts_with(prefix.as_str()) {
+                // A lagging receiver is not a reason to fail the commit.
+                let _ = tx.send(ChangeEvent {
+                    key: key.to_string(),
+                    value: value.clone(),
+                });
+            }
+            true
+        });
+    }
+
+    /// As `publish`, but for nonconsensus keys: prefixes are matched as raw
+    /// bytes via `starts_with`, with no UTF-8 conversion in either direction.
+    fn publish_nonconsensus(&self, key: &[u8], value: &Option<Vec<u8>>) {
+        let mut subscribers = self
+            .nonconsensus_subscribers
+            .lock()
+            .expect("subscriptions mutex is not poisoned");
+        subscribers.retain(|(prefix, tx)| {
+            if tx.receiver_count() == 0 {
+                return false;
+            }
+            if key.starts_with(prefix.as_slice()) {
+                // A lagging receiver is not a reason to fail the commit.
+                let _ = tx.send(NonconsensusChangeEvent {
+                    key: key.to_vec(),
+                    value: value.clone(),
+                });
+            }
+            true
+        });
+    }
+}
+
 /// Represents a transactional set of changes to a `State` fork,
 /// implemented as a RYW cache over a `State`.
 pub struct Transaction<'a> {
@@ -14,15 +150,17 @@ pub struct Transaction<'a> {
     pub(crate) unwritten_changes: BTreeMap<String, Option<Vec<u8>>>,
     /// Unwritten changes to non-consensus-critical state (stored in the nonconsensus storage).
     pub(crate) nonconsensus_changes: BTreeMap<Vec<u8>, Option<Vec<u8>>>,
-    state: &'a mut State,
+    pub(crate) state: &'a mut State,
+    subscriptions: ChangeSubscriptions,
     pub(crate) failed: bool,
     pub(crate) failure_reason: String,
 }
 
 impl<'a> Transaction<'a> {
-    pub fn new(state: &'a mut State) -> Self {
+    pub fn new(state: &'a mut State, subscriptions: ChangeSubscriptions) -> Self {
         Self {
             state,
+            subscriptions,
             unwritten_changes: BTreeMap::new(),
             nonconsensus_changes: BTreeMap::new(),
             failed: false,
@@ -40,6 +178,16 @@ impl<'a> Transaction<'a> {
             return Err(anyhow::anyhow!("transaction failed").context(self.failure_reason));
         }
 
+        // Publish each consensus and nonconsensus change to matching subscribers
+        // before folding it into `State`, so the commit path is the single source
+        // of truth for both the stored value and the change-event stream.
+        for (key, value) in self.unwritten_changes.iter() {
+            self.subscriptions.publish(key, value);
+        }
+        for (key, value) in self.nonconsensus_changes.iter() {
+            self.subscriptions.publish_nonconsensus(key, value);
+        }
+
         // Write the unwritten consensus-critical changes to the state:
         self.state.unwritten_changes.extend(self.unwritten_changes);
 
@@ -50,6 +198,149 @@ impl<'a> Transaction<'a> {
 
         Ok(())
     }
+
+    /// Begins a nested transaction ("savepoint") on top of this transaction.
+    ///
+    /// Writes made through the returned [`NestedTransaction`] are applied directly
+    /// to this transaction's overlay, so they're immediately visible through the
+    /// usual read-your-writes semantics. They can later be discarded all at once
+    /// with `rollback_nested` (or by dropping the `NestedTransaction`), without
+    /// aborting this transaction, or folded in permanently with `commit_nested`.
+    pub fn begin_nested(&mut self) -> NestedTransaction<'_, 'a> {
+        NestedTransaction {
+            tx: self,
+            saved_unwritten: BTreeMap::new(),
+            saved_nonconsensus: BTreeMap::new(),
+            committed: false,
+        }
+    }
+}
+
+/// A savepoint over a [`Transaction`], allowing a batch of writes to be applied and
+/// then either folded into the parent transaction or rolled back as a unit.
+///
+/// Rather than cloning the parent's overlay, a `NestedTransaction` keeps an undo log
+/// of the keys it has touched and their prior values, so that rolling back only
+/// needs to replay that log instead of discarding and rebuilding the whole overlay.
+pub struct NestedTransaction<'tx, 'a> {
+    tx: &'tx mut Transaction<'a>,
+    /// For each consensus-critical key touched in this scope, the value it had in
+    /// `tx.unwritten_changes` before this scope started (`None` if it wasn't
+    /// present, meaning the key should be removed entirely on rollback).
+    saved_unwritten: BTreeMap<String, Option<Option<Vec<u8>>>>,
+    /// As `saved_unwritten`, but for nonconsensus keys.
+    saved_nonconsensus: BTreeMap<Vec<u8>, Option<Option<Vec<u8>>>>,
+    committed: bool,
+}
+
+impl<'tx, 'a> NestedTransaction<'tx, 'a> {
+    fn record_unwritten(&mut self, key: &str) {
+        if !self.saved_unwritten.contains_key(key) {
+            let prev = self.tx.unwritten_changes.get(key).cloned();
+            self.saved_unwritten.insert(key.to_string(), prev);
+        }
+    }
+
+    fn record_nonconsensus(&mut self, key: &[u8]) {
+        if !self.saved_nonconsensus.contains_key(key) {
+            let prev = self.tx.nonconsensus_changes.get(key).cloned();
+            self.saved_nonconsensus.insert(key.to_vec(), prev);
+        }
+    }
+
+    fn rollback(&mut self) {
+        for (key, prev) in std::mem::take(&mut self.saved_unwritten) {
+            match prev {
+                Some(v) => {
+                    self.tx.unwritten_changes.insert(key, v);
+                }
+                None => {
+                    self.tx.unwritten_changes.remove(&key);
+                }
+            }
+        }
+        for (key, prev) in std::mem::take(&mut self.saved_nonconsensus) {
+            match prev {
+                Some(v) => {
+                    self.tx.nonconsensus_changes.insert(key, v);
+                }
+                None => {
+                    self.tx.nonconsensus_changes.remove(&key);
+                }
+            }
+        }
+    }
+
+    /// Folds this nested transaction's writes into its parent.
+    ///
+    /// Because writes made through a `NestedTransaction` are applied directly to
+    /// the parent's overlay as they happen, committing is just a matter of
+    /// discarding the undo log without replaying it.
+    pub fn commit_nested(mut self) {
+        self.committed = true;
+    }
+
+    /// Discards this nested transaction's writes, restoring the parent's state to
+    /// what it was before `begin_nested` was called.
+    pub fn rollback_nested(mut self) {
+        self.rollback();
+        self.committed = true;
+    }
+}
+
+impl<'tx, 'a> Drop for NestedTransaction<'tx, 'a> {
+    fn drop(&mut self) {
+        if !self.committed {
+            self.rollback();
+        }
+    }
+}
+
+impl<'tx, 'a> StateWrite for NestedTransaction<'tx, 'a> {
+    fn put_raw(&mut self, key: String, value: jmt::OwnedValue) {
+        self.record_unwritten(&key);
+        self.tx.put_raw(key, value);
+    }
+
+    fn delete(&mut self, key: String) {
+        self.record_unwritten(&key);
+        self.tx.delete(key);
+    }
+
+    fn delete_nonconsensus(&mut self, key: Vec<u8>) {
+        self.record_nonconsensus(&key);
+        self.tx.delete_nonconsensus(key);
+    }
+
+    fn put_nonconsensus(&mut self, key: Vec<u8>, value: Vec<u8>) {
+        self.record_nonconsensus(&key);
+        self.tx.put_nonconsensus(key, value);
+    }
+}
+
+#[async_trait]
+impl<'tx, 'a> StateRead for NestedTransaction<'tx, 'a> {
+    async fn get_raw(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        self.tx.get_raw(key).await
+    }
+
+    async fn get_nonconsensus(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        self.tx.get_nonconsensus(key).await
+    }
+
+    async fn prefix_raw<'b>(
+        &'b self,
+        prefix: &'b str,
+    ) -> Pin<Box<dyn Stream<Item = Result<(String, Box<[u8]>)>> + Sync + Send + 'b>> {
+        self.tx.prefix_raw(prefix).await
+    }
+
+    fn subscribe_prefix<'b>(
+        &'b self,
+        prefix: &'b str,
+    ) -> Pin<Box<dyn Stream<Item = Result<(String, Option<Vec<u8>>)>> + Sync + Send + 'b>> {
+        self.tx.subscribe_prefix(prefix)
+    }
 }
 
 impl<'a> StateWrite for Transaction<'a> {
@@ -96,6 +387,101 @@ impl<'tx> StateRead for Transaction<'tx> {
         &'a self,
         prefix: &'a str,
     ) -> Pin<Box<dyn Stream<Item = Result<(String, Box<[u8]>)>> + Sync + Send + 'a>> {
-        prefix_raw_with_cache(self, &self.unwritten_changes, prefix).await
+        prefix_raw_with_cache(self.state, &self.unwritten_changes, prefix).await
+    }
+
+    fn subscribe_prefix<'a>(
+        &'a self,
+        prefix: &'a str,
+    ) -> Pin<Box<dyn Stream<Item = Result<(String, Option<Vec<u8>>)>> + Sync + Send + 'a>> {
+        self.subscriptions.subscribe_prefix(prefix)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::snapshot::{Snapshot, SnapshotCache};
+    use crate::State;
+
+    use super::*;
+
+    /// A `State` over an empty, throwaway RocksDB instance. Fine for tests that
+    /// only touch the RYW overlay and never fall through to the underlying
+    /// `Snapshot`.
+    fn test_state() -> State {
+        let dir = tempfile::tempdir().expect("can create temp dir");
+        let db = Arc::new(rocksdb::DB::open_default(dir.path()).expect("can open rocksdb"));
+        let snapshot = Snapshot::new(db, 0, SnapshotCache::new(100));
+        State::new(snapshot, ChangeSubscriptions::new())
+    }
+
+    #[tokio::test]
+    async fn nested_transaction_rollback_restores_parent_value() -> Result<()> {
+        let mut state = test_state();
+        let mut tx = state.begin_transaction();
+        tx.put_raw("key".to_string(), b"parent_value".to_vec());
+
+        {
+            let mut nested = tx.begin_nested();
+            nested.put_raw("key".to_string(), b"nested_value".to_vec());
+            assert_eq!(
+                nested.get_raw("key").await?,
+                Some(b"nested_value".to_vec())
+            );
+            nested.rollback_nested();
+        }
+
+        assert_eq!(tx.get_raw("key").await?, Some(b"parent_value".to_vec()));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn nested_transaction_rollback_restores_absence() -> Result<()> {
+        let mut state = test_state();
+        let mut tx = state.begin_transaction();
+
+        {
+            let mut nested = tx.begin_nested();
+            nested.put_raw("key".to_string(), b"nested_value".to_vec());
+            nested.rollback_nested();
+        }
+
+        // The key was never in the parent's overlay before the nested scope, so
+        // rolling back should remove it entirely rather than restoring `None`
+        // as an explicit (and distinct) pending-delete marker.
+        assert!(!tx.unwritten_changes.contains_key("key"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn nested_transaction_drop_without_commit_rolls_back() -> Result<()> {
+        let mut state = test_state();
+        let mut tx = state.begin_transaction();
+        tx.put_raw("key".to_string(), b"parent_value".to_vec());
+
+        {
+            let mut nested = tx.begin_nested();
+            nested.put_raw("key".to_string(), b"nested_value".to_vec());
+            // Dropped without calling `commit_nested` or `rollback_nested`.
+        }
+
+        assert_eq!(tx.get_raw("key").await?, Some(b"parent_value".to_vec()));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn nested_transaction_commit_keeps_writes() -> Result<()> {
+        let mut state = test_state();
+        let mut tx = state.begin_transaction();
+        tx.put_raw("key".to_string(), b"parent_value".to_vec());
+
+        {
+            let mut nested = tx.begin_nested();
+            nested.put_raw("key".to_string(), b"nested_value".to_vec());
+            nested.commit_nested();
+        }
+
+        assert_eq!(tx.get_raw("key").await?, Some(b"nested_value".to_vec()));
+        Ok(())
     }
 }
@@ -0,0 +1,72 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use jmt::storage::TreeReader;
+
+use crate::snapshot::{PruningMode, Snapshot, SnapshotCache, VersionPruned};
+
+/// Opens and manages the RocksDB-backed storage underlying `State`, and hands out
+/// read-only [`Snapshot`]s pinned to a particular JMT version.
+pub struct Storage {
+    db: Arc<rocksdb::DB>,
+    cache: SnapshotCache,
+    pruning: PruningMode,
+}
+
+impl Storage {
+    pub(crate) fn new(db: Arc<rocksdb::DB>, cache_capacity: u64, pruning: PruningMode) -> Self {
+        Self {
+            db,
+            cache: SnapshotCache::new(cache_capacity),
+            pruning,
+        }
+    }
+
+    /// Returns a [`Snapshot`] pinned to the latest committed version.
+    pub fn latest_snapshot(&self) -> Result<Snapshot> {
+        let version = self.latest_version()?;
+        Ok(Snapshot::new(self.db.clone(), version, self.cache.clone()))
+    }
+
+    /// Opens a read-only [`Snapshot`] pinned to `version`, for "what was the value
+    /// of this key at height N" queries from RPC and the `pcli`/`pd` query
+    /// tooling.
+    ///
+    /// Only reads that walk the JMT at the pinned version (`get_raw_with_proof`)
+    /// reflect `version`. `get_raw`, `get_nonconsensus` and `prefix_raw` look keys
+    /// up directly in RocksDB and are not filtered by `version`.
+    ///
+    /// Returns [`VersionPruned`] if `version` is older than what this storage's
+    /// configured [`PruningMode`] retains, and an error if `version` is newer than
+    /// the latest committed version (i.e. it doesn't exist yet).
+    pub fn snapshot_at(&self, version: jmt::Version) -> Result<Snapshot> {
+        let latest = self.latest_version()?;
+        anyhow::ensure!(
+            version <= latest,
+            "version {version} has not been committed yet; latest version is {latest}"
+        );
+        self.pruning
+            .ensure_available(version, latest)
+            .map_err(|e: VersionPruned| anyhow::Error::new(e))?;
+        Ok(Snapshot::new(self.db.clone(), version, self.cache.clone()))
+    }
+
+    /// One JMT version is committed per block, so a block height and the version
+    /// of the state it produced coincide.
+    pub fn version_for_height(&self, height: u64) -> jmt::Version {
+        height as jmt::Version
+    }
+
+    /// Returns the most recently committed JMT version, derived from the
+    /// rightmost leaf's node key, per `TreeReader::get_rightmost_leaf`.
+    fn latest_version(&self) -> Result<jmt::Version> {
+        // The version pinned on this placeholder snapshot is irrelevant: unlike
+        // `get_node_option`, `get_rightmost_leaf` isn't filtered by version, it
+        // just seeks to the last entry in the `jmt` column family.
+        let reader = Snapshot::new(self.db.clone(), 0, self.cache.clone());
+        match reader.get_rightmost_leaf()? {
+            Some((node_key, _)) => Ok(node_key.version()),
+            None => Ok(0),
+        }
+    }
+}